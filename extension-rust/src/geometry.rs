@@ -1,67 +1,174 @@
-use std::ops::{Add, Div, Mul, Sub};
+use std::marker::PhantomData;
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign,
+};
 
-#[derive(Debug, PartialEq)]
-pub struct Point {
+/// Marker for points whose coordinate reference system is not tracked.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct UnknownUnit;
+
+/// Geographic coordinates as WGS84 longitude/latitude degrees.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Wgs84;
+
+/// Projected coordinates in metres.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Meters;
+
+/// A point in a geographic coordinate reference system.
+pub type LngLat = Point<Wgs84>;
+/// A point in a projected, metre-based coordinate reference system.
+pub type Projected = Point<Meters>;
+
+/// A 2D point tagged with the unit `U` of its coordinate reference system.
+///
+/// The phantom marker lets the compiler reject mixing, say, lon/lat degrees
+/// with projected metres: the arithmetic impls only join two points of the
+/// same `U`. Use [`Point::cast_unit`] to deliberately reinterpret a point in a
+/// different unit.
+pub struct Point<U = UnknownUnit> {
     pub x: f64,
     pub y: f64,
+    _unit: PhantomData<U>,
+}
+
+// Derive would pin unnecessary `U: Trait` bounds, so the marker-independent
+// traits are implemented by hand.
+impl<U> Clone for Point<U> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<U> Copy for Point<U> {}
+
+impl<U> std::fmt::Debug for Point<U> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Point")
+            .field("x", &self.x)
+            .field("y", &self.y)
+            .finish()
+    }
+}
+
+impl<U> PartialEq for Point<U> {
+    fn eq(&self, other: &Self) -> bool {
+        self.x == other.x && self.y == other.y
+    }
 }
 
-impl Point {
-    fn origin() -> Point {
-        Point { x: 0.0, y: 0.0 }
+impl<U> Point<U> {
+    pub fn new(x: f64, y: f64) -> Point<U> {
+        Point {
+            x,
+            y,
+            _unit: PhantomData,
+        }
+    }
+    fn origin() -> Point<U> {
+        Point::new(0.0, 0.0)
+    }
+    /// Reinterpret these coordinates as belonging to unit `V`.
+    fn cast_unit<V>(self) -> Point<V> {
+        Point::new(self.x, self.y)
     }
     fn magnitude(self) -> f64 {
         (self.x * self.x + self.y * self.y).sqrt()
     }
+    fn dot(self, other: Point<U>) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+    fn cross(self, other: Point<U>) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+    fn distance(self, other: Point<U>) -> f64 {
+        (self - other).magnitude()
+    }
+    fn lerp(self, other: Point<U>, t: f64) -> Point<U> {
+        self + (other - self) * t
+    }
+    fn midpoint(self, other: Point<U>) -> Point<U> {
+        self.lerp(other, 0.5)
+    }
+    /// Unit vector in the direction of `self`; a point with near-zero
+    /// magnitude is returned unchanged rather than producing NaNs.
+    fn normalize(self) -> Point<U> {
+        let m = self.magnitude();
+        if m < 1e-12 {
+            self
+        } else {
+            self / m
+        }
+    }
 }
 
-impl Add for Point {
+impl<U> Add for Point<U> {
     type Output = Self;
     fn add(self, other: Self) -> Self {
-        Self {
-            x: self.x + other.x,
-            y: self.y + other.y,
-        }
+        Point::new(self.x + other.x, self.y + other.y)
     }
 }
 
-impl Sub for Point {
+impl<U> Sub for Point<U> {
     type Output = Self;
     fn sub(self, other: Self) -> Self {
-        Self {
-            x: self.x - other.x,
-            y: self.y - other.y,
-        }
+        Point::new(self.x - other.x, self.y - other.y)
     }
 }
 
-impl Mul<f64> for Point {
+impl<U> Mul<f64> for Point<U> {
     type Output = Self;
     fn mul(self, other: f64) -> Self {
-        Self {
-            x: self.x * other,
-            y: self.y * other,
-        }
+        Point::new(self.x * other, self.y * other)
     }
 }
 
-impl Mul<Point> for f64 {
-    type Output = Point;
-    fn mul(self, other: Point) -> Point {
-        Point {
-            x: self * other.x,
-            y: self * other.y,
-        }
+impl<U> Mul<Point<U>> for f64 {
+    type Output = Point<U>;
+    fn mul(self, other: Point<U>) -> Point<U> {
+        Point::new(self * other.x, self * other.y)
     }
 }
 
-impl Div<f64> for Point {
+impl<U> Div<f64> for Point<U> {
     type Output = Self;
     fn div(self, other: f64) -> Self {
-        Self {
-            x: self.x / other,
-            y: self.y / other,
-        }
+        Point::new(self.x / other, self.y / other)
+    }
+}
+
+impl<U> Neg for Point<U> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl<U> AddAssign for Point<U> {
+    fn add_assign(&mut self, other: Self) {
+        self.x += other.x;
+        self.y += other.y;
+    }
+}
+
+impl<U> SubAssign for Point<U> {
+    fn sub_assign(&mut self, other: Self) {
+        self.x -= other.x;
+        self.y -= other.y;
+    }
+}
+
+impl<U> MulAssign<f64> for Point<U> {
+    fn mul_assign(&mut self, other: f64) {
+        self.x *= other;
+        self.y *= other;
+    }
+}
+
+impl<U> DivAssign<f64> for Point<U> {
+    fn div_assign(&mut self, other: f64) {
+        self.x /= other;
+        self.y /= other;
     }
 }
 
@@ -76,46 +183,409 @@ impl Segment {
         let dy = self.end.y - self.start.y;
         (dx * dx + dy * dy).sqrt()
     }
+
+    /// Cut the segment wherever it crosses a grid line of the raster whose
+    /// cells are `cell_width` by `cell_height` and anchored at `origin`.
+    ///
+    /// The segment is written parametrically as `P(t) = start + t*(end-start)`
+    /// for `t` in `[0, 1]`; crossing parameters are collected for the vertical
+    /// grid lines `k*cell_width + origin.x` and the horizontal lines
+    /// `k*cell_height + origin.y` that fall strictly inside the segment. The
+    /// returned sub-segments are ordered from `start` to `end` and join back up
+    /// into the original segment.
+    fn split_on_grid(&self, cell_width: f64, cell_height: f64, origin: Point) -> Vec<Segment> {
+        const EPS: f64 = 1e-9;
+        let dx = self.end.x - self.start.x;
+        let dy = self.end.y - self.start.y;
+
+        let mut crossings: Vec<f64> = Vec::new();
+        if dx != 0.0 {
+            let (lo, hi) = (self.start.x.min(self.end.x), self.start.x.max(self.end.x));
+            let k_lo = ((lo - origin.x) / cell_width).floor() as i64;
+            let k_hi = ((hi - origin.x) / cell_width).ceil() as i64;
+            for k in k_lo..=k_hi {
+                let line = k as f64 * cell_width + origin.x;
+                if line > lo && line < hi {
+                    crossings.push((line - self.start.x) / dx);
+                }
+            }
+        }
+        if dy != 0.0 {
+            let (lo, hi) = (self.start.y.min(self.end.y), self.start.y.max(self.end.y));
+            let k_lo = ((lo - origin.y) / cell_height).floor() as i64;
+            let k_hi = ((hi - origin.y) / cell_height).ceil() as i64;
+            for k in k_lo..=k_hi {
+                let line = k as f64 * cell_height + origin.y;
+                if line > lo && line < hi {
+                    crossings.push((line - self.start.y) / dy);
+                }
+            }
+        }
+
+        crossings.retain(|&t| t > EPS && t < 1.0 - EPS);
+        crossings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        crossings.dedup_by(|a, b| (*a - *b).abs() < EPS);
+
+        let mut ts = Vec::with_capacity(crossings.len() + 2);
+        ts.push(0.0);
+        ts.extend(crossings);
+        ts.push(1.0);
+
+        let point_at = |t: f64| Point::new(self.start.x + t * dx, self.start.y + t * dy);
+        ts.windows(2)
+            .map(|w| Segment {
+                start: point_at(w[0]),
+                end: point_at(w[1]),
+            })
+            .collect()
+    }
+
+    /// Find where this segment crosses `other`.
+    ///
+    /// Writing the segments as `p + t*r` and `q + u*s`, the crossing solves
+    /// `denom = r.cross(s)`. Parallel or collinear segments (`denom` ~ 0) are
+    /// reported as no intersection. Otherwise the crossing point is returned
+    /// together with its parameters `t` and `u`, provided both lie in `[0, 1]`.
+    fn intersection(&self, other: &Segment) -> Option<(Point, f64, f64)> {
+        const EPS: f64 = 1e-12;
+        let p = self.start;
+        let r = self.end - self.start;
+        let q = other.start;
+        let s = other.end - other.start;
+
+        let denom = r.cross(s);
+        if denom.abs() < EPS {
+            return None;
+        }
+
+        let t = (q - p).cross(s) / denom;
+        let u = (q - p).cross(r) / denom;
+        if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+            Some((p + r * t, t, u))
+        } else {
+            None
+        }
+    }
+}
+
+/// A simple polygon: one exterior ring and zero or more hole rings.
+///
+/// Rings are stored as open vertex lists (the closing edge from the last
+/// vertex back to the first is implied). Winding order is not enforced; the
+/// point-in-polygon test uses the even-odd rule so either order works.
+pub struct Polygon {
+    pub exterior: Vec<Point>,
+    pub holes: Vec<Vec<Point>>,
+}
+
+/// Count the crossings of the horizontal ray from `p` to `+x` against `ring`
+/// and report whether the count is odd. Edges are treated half-open with the
+/// `(y0 <= p.y) != (y1 <= p.y)` convention so a vertex exactly on the ray is
+/// not counted twice.
+fn ray_crosses_odd(ring: &[Point], p: &Point) -> bool {
+    let mut inside = false;
+    let n = ring.len();
+    for i in 0..n {
+        let a = ring[i];
+        let b = ring[(i + 1) % n];
+        if (a.y <= p.y) != (b.y <= p.y) {
+            let x = a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if x > p.x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+impl Polygon {
+    /// Even-odd ray-casting point-in-polygon test: a point inside the exterior
+    /// ring but inside any hole ring is reported as outside the polygon.
+    fn contains(&self, p: &Point) -> bool {
+        if !ray_crosses_odd(&self.exterior, p) {
+            return false;
+        }
+        !self.holes.iter().any(|hole| ray_crosses_odd(hole, p))
+    }
+
+    /// Signed area of the exterior ring via the shoelace formula. Positive for
+    /// counter-clockwise winding, negative for clockwise.
+    fn signed_area(&self) -> f64 {
+        let ring = &self.exterior;
+        let n = ring.len();
+        let mut sum = 0.0;
+        for i in 0..n {
+            let a = ring[i];
+            let b = ring[(i + 1) % n];
+            sum += a.x * b.y - b.x * a.y;
+        }
+        sum / 2.0
+    }
+
+    /// Return the portions of `seg` that lie inside the polygon.
+    ///
+    /// The segment is split at every crossing with an exterior or hole edge;
+    /// each resulting interval whose midpoint is contained is emitted as a
+    /// sub-segment, so a segment entering and leaving through a hole yields
+    /// two pieces.
+    fn clip_segment(&self, seg: &Segment) -> Vec<Segment> {
+        const EPS: f64 = 1e-9;
+        let dir = seg.end - seg.start;
+
+        let mut ts: Vec<f64> = vec![0.0, 1.0];
+        let rings = std::iter::once(&self.exterior).chain(self.holes.iter());
+        for ring in rings {
+            let n = ring.len();
+            for i in 0..n {
+                let edge = Segment {
+                    start: ring[i],
+                    end: ring[(i + 1) % n],
+                };
+                if let Some((_, t, _)) = seg.intersection(&edge) {
+                    ts.push(t);
+                }
+            }
+        }
+
+        ts.retain(|&t| (0.0..=1.0).contains(&t));
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        ts.dedup_by(|a, b| (*a - *b).abs() < EPS);
+
+        let point_at = |t: f64| seg.start + dir * t;
+        ts.windows(2)
+            .filter(|w| w[1] - w[0] > EPS && self.contains(&point_at((w[0] + w[1]) / 2.0)))
+            .map(|w| Segment {
+                start: point_at(w[0]),
+                end: point_at(w[1]),
+            })
+            .collect()
+    }
 }
 
 mod tests {
     use super::*;
 
+    fn unit_square() -> Polygon {
+        Polygon {
+            exterior: vec![
+                Point::new(0.0, 0.0),
+                Point::new(1.0, 0.0),
+                Point::new(1.0, 1.0),
+                Point::new(0.0, 1.0),
+            ],
+            holes: vec![],
+        }
+    }
+
+    #[test]
+    fn polygon_contains() {
+        let sq = unit_square();
+        assert!(sq.contains(&Point::new(0.5, 0.5)));
+        assert!(!sq.contains(&Point::new(1.5, 0.5)));
+        assert!(!sq.contains(&Point::new(-0.5, 0.5)));
+    }
+
+    #[test]
+    fn polygon_contains_hole() {
+        let mut sq = unit_square();
+        sq.exterior = vec![
+            Point::new(0.0, 0.0),
+            Point::new(4.0, 0.0),
+            Point::new(4.0, 4.0),
+            Point::new(0.0, 4.0),
+        ];
+        sq.holes = vec![vec![
+            Point::new(1.0, 1.0),
+            Point::new(3.0, 1.0),
+            Point::new(3.0, 3.0),
+            Point::new(1.0, 3.0),
+        ]];
+        assert!(sq.contains(&Point::new(0.5, 2.0)));
+        assert!(!sq.contains(&Point::new(2.0, 2.0)));
+    }
+
+    #[test]
+    fn polygon_signed_area() {
+        assert_eq!(1.0, unit_square().signed_area());
+    }
+
+    #[test]
+    fn polygon_clip_segment() {
+        let sq = unit_square();
+        let seg = Segment {
+            start: Point::new(-1.0, 0.5),
+            end: Point::new(2.0, 0.5),
+        };
+        let parts = sq.clip_segment(&seg);
+        assert_eq!(1, parts.len());
+        assert_eq!(Point::new(0.0, 0.5), parts[0].start);
+        assert_eq!(Point::new(1.0, 0.5), parts[0].end);
+    }
+
     #[test]
     fn origin() {
-        assert_eq!(Point { x: 0.0, y: 0.0 }, Point::origin());
+        assert_eq!(Point::<UnknownUnit>::new(0.0, 0.0), Point::origin());
     }
 
     #[test]
     fn magnitude() {
-        assert_eq!(5.0, Point { y: 3.0, x: 4.0 }.magnitude());
-        assert_eq!(13.0, Point { y: 5.0, x: 12.0 }.magnitude());
-        assert_eq!(65.0, Point { y: -33.0, x: -56.0 }.magnitude());
+        assert_eq!(5.0, Point::<UnknownUnit>::new(4.0, 3.0).magnitude());
+        assert_eq!(13.0, Point::<UnknownUnit>::new(12.0, 5.0).magnitude());
+        assert_eq!(65.0, Point::<UnknownUnit>::new(-56.0, -33.0).magnitude());
     }
 
     #[test]
     fn add() {
-        let a = Point { x: 1.0, y: 2.0 };
-        let b = Point { x: 1.0, y: 0.0 };
-        assert_eq!(a + b, Point { x: 2.0, y: 2.0 });
+        let a = Point::<UnknownUnit>::new(1.0, 2.0);
+        let b = Point::new(1.0, 0.0);
+        assert_eq!(a + b, Point::new(2.0, 2.0));
     }
 
     #[test]
     fn subtract() {
-        let a = Point { x: 1.0, y: 2.0 };
-        let b = Point { x: 1.0, y: 0.0 };
-        assert_eq!(b - a, Point { x: 0.0, y: -2.0 });
+        let a = Point::<UnknownUnit>::new(1.0, 2.0);
+        let b = Point::new(1.0, 0.0);
+        assert_eq!(b - a, Point::new(0.0, -2.0));
     }
 
     #[test]
     fn mul() {
-        assert_eq!(Point { x: 1.3, y: 2.0 } * 4.0, Point { x: 5.2, y: 8.0 });
-        assert_eq!(4.0 * Point { x: 1.3, y: 2.0 }, Point { x: 5.2, y: 8.0 });
+        assert_eq!(Point::<UnknownUnit>::new(1.3, 2.0) * 4.0, Point::new(5.2, 8.0));
+        assert_eq!(4.0 * Point::<UnknownUnit>::new(1.3, 2.0), Point::new(5.2, 8.0));
     }
 
     #[test]
     fn div() {
-        assert_eq!(Point { x: 7.0, y: 2.0 } / 2.0, Point { x: 3.5, y: 1.0 });
+        assert_eq!(Point::<UnknownUnit>::new(7.0, 2.0) / 2.0, Point::new(3.5, 1.0));
+    }
+
+    #[test]
+    fn cast_unit_preserves_coordinates() {
+        let ll: LngLat = Point::new(1.0, 2.0);
+        let projected: Projected = ll.cast_unit();
+        assert_eq!(Point::<Meters>::new(1.0, 2.0), projected);
+    }
+
+    #[test]
+    fn dot_and_cross() {
+        let a = Point::<UnknownUnit>::new(1.0, 2.0);
+        let b = Point::new(3.0, 4.0);
+        assert_eq!(11.0, a.dot(b));
+        assert_eq!(-2.0, a.cross(b));
+    }
+
+    #[test]
+    fn distance() {
+        let a = Point::<UnknownUnit>::new(0.0, 0.0);
+        let b = Point::new(3.0, 4.0);
+        assert_eq!(5.0, a.distance(b));
+    }
+
+    #[test]
+    fn lerp_and_midpoint() {
+        let a = Point::<UnknownUnit>::new(0.0, 0.0);
+        let b = Point::new(4.0, 8.0);
+        assert_eq!(Point::new(1.0, 2.0), a.lerp(b, 0.25));
+        assert_eq!(Point::new(2.0, 4.0), a.midpoint(b));
+    }
+
+    #[test]
+    fn normalize() {
+        assert_eq!(Point::new(1.0, 0.0), Point::<UnknownUnit>::new(5.0, 0.0).normalize());
+        // a near-zero point is left untouched
+        let tiny = Point::<UnknownUnit>::new(0.0, 0.0);
+        assert_eq!(tiny, tiny.normalize());
+    }
+
+    #[test]
+    fn neg_and_assign_ops() {
+        assert_eq!(Point::<UnknownUnit>::new(-1.0, 2.0), -Point::new(1.0, -2.0));
+        let mut p = Point::<UnknownUnit>::new(1.0, 1.0);
+        p += Point::new(2.0, 3.0);
+        assert_eq!(Point::new(3.0, 4.0), p);
+        p -= Point::new(1.0, 1.0);
+        assert_eq!(Point::new(2.0, 3.0), p);
+        p *= 2.0;
+        assert_eq!(Point::new(4.0, 6.0), p);
+        p /= 2.0;
+        assert_eq!(Point::new(2.0, 3.0), p);
+    }
+
+    #[test]
+    fn intersection_crossing() {
+        let a = Segment {
+            start: Point::new(0.0, 0.0),
+            end: Point::new(2.0, 2.0),
+        };
+        let b = Segment {
+            start: Point::new(0.0, 2.0),
+            end: Point::new(2.0, 0.0),
+        };
+        let (point, t, u) = a.intersection(&b).unwrap();
+        assert_eq!(Point::new(1.0, 1.0), point);
+        assert_eq!(0.5, t);
+        assert_eq!(0.5, u);
+    }
+
+    #[test]
+    fn intersection_parallel() {
+        let a = Segment {
+            start: Point::new(0.0, 0.0),
+            end: Point::new(2.0, 0.0),
+        };
+        let b = Segment {
+            start: Point::new(0.0, 1.0),
+            end: Point::new(2.0, 1.0),
+        };
+        assert_eq!(None, a.intersection(&b));
+    }
+
+    #[test]
+    fn intersection_out_of_range() {
+        let a = Segment {
+            start: Point::new(0.0, 0.0),
+            end: Point::new(1.0, 1.0),
+        };
+        let b = Segment {
+            start: Point::new(2.0, 0.0),
+            end: Point::new(2.0, 4.0),
+        };
+        assert_eq!(None, a.intersection(&b));
+    }
+
+    #[test]
+    fn split_on_grid_crosses_both_axes() {
+        let seg = Segment {
+            start: Point::origin(),
+            end: Point::new(2.0, 2.0),
+        };
+        let parts = seg.split_on_grid(1.0, 1.0, Point::origin());
+        // crosses x=1 and y=1 at the same point (t=0.5), merged to one corner
+        assert_eq!(2, parts.len());
+        assert_eq!(parts[0].start, Point::origin());
+        assert_eq!(parts[0].end, Point::new(1.0, 1.0));
+        assert_eq!(parts[1].end, Point::new(2.0, 2.0));
+    }
+
+    #[test]
+    fn split_on_grid_vertical_only() {
+        let seg = Segment {
+            start: Point::new(0.5, 0.5),
+            end: Point::new(3.5, 0.5),
+        };
+        let parts = seg.split_on_grid(1.0, 1.0, Point::origin());
+        // crosses x = 1, 2, 3
+        assert_eq!(4, parts.len());
+    }
+
+    #[test]
+    fn split_on_grid_no_crossing() {
+        let seg = Segment {
+            start: Point::new(0.2, 0.2),
+            end: Point::new(0.8, 0.8),
+        };
+        let parts = seg.split_on_grid(1.0, 1.0, Point::origin());
+        assert_eq!(1, parts.len());
     }
 
     #[test]
@@ -132,15 +602,15 @@ mod tests {
             5.0,
             Segment {
                 start: Point::origin(),
-                end: Point { x: 3.0, y: 4.0 }
+                end: Point::new(3.0, 4.0)
             }
             .length()
         );
         assert_eq!(
             13.0,
             Segment {
-                start: Point { x: 17.0, y: 4.0 },
-                end: Point { x: 22.0, y: 16.0 }
+                start: Point::new(17.0, 4.0),
+                end: Point::new(22.0, 16.0)
             }
             .length()
         );